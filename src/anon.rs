@@ -0,0 +1,43 @@
+use sha2::{Digest, Sha256};
+
+/// Vervangt een lid-id door een gezouten SHA-256 digest, zodat hetzelfde lid
+/// stabiel pseudoniem blijft (ook tussen runs met hetzelfde zout) zonder de
+/// identiteit van het lid bloot te geven.
+pub fn pseudonymize(salt: &str, member_id: i32) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(b":");
+    hasher.update(member_id.to_string().as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pseudonymize;
+
+    #[test]
+    fn same_salt_and_member_id_is_stable() {
+        assert_eq!(
+            pseudonymize("zout", 42),
+            pseudonymize("zout", 42)
+        );
+    }
+
+    #[test]
+    fn different_member_ids_produce_different_tokens() {
+        assert_ne!(pseudonymize("zout", 42), pseudonymize("zout", 43));
+    }
+
+    #[test]
+    fn different_salts_produce_different_tokens() {
+        assert_ne!(pseudonymize("zout-a", 42), pseudonymize("zout-b", 42));
+    }
+
+    #[test]
+    fn output_is_a_lowercase_hex_sha256_digest() {
+        let token = pseudonymize("zout", 42);
+
+        assert_eq!(token.len(), 64);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+    }
+}
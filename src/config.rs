@@ -0,0 +1,29 @@
+use color_eyre::Result;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Connectiegegevens die als aanvulling op argumenten en omgevingsvariabelen
+/// uit een TOML config-bestand gelezen kunnen worden (`--config`). Velden die
+/// hier niet gezet zijn, vallen terug op de CLI-argumenten of hun default.
+#[derive(Debug, Default, Deserialize)]
+pub struct FileConfig {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub sslmode: Option<String>,
+    pub db_name: Option<String>,
+    pub db_user: Option<String>,
+    pub db_password: Option<String>,
+}
+
+impl FileConfig {
+    /// Leest `path` in als TOML config-bestand, of geeft een lege config
+    /// terug als er geen pad is opgegeven.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+
+        let contents = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+}
@@ -0,0 +1,41 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "educations")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub member_id: i32,
+    pub study_id: i32,
+    pub status: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::members::Entity",
+        from = "Column::MemberId",
+        to = "super::members::Column::Id"
+    )]
+    Members,
+    #[sea_orm(
+        belongs_to = "super::studies::Entity",
+        from = "Column::StudyId",
+        to = "super::studies::Column::Id"
+    )]
+    Studies,
+}
+
+impl Related<super::members::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Members.def()
+    }
+}
+
+impl Related<super::studies::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Studies.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,42 @@
+use sea_orm::entity::prelude::*;
+use time::Date;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "members")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub join_date: Date,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::educations::Entity")]
+    Educations,
+    #[sea_orm(has_many = "super::group_members::Entity")]
+    GroupMembers,
+    #[sea_orm(has_many = "super::participants::Entity")]
+    Participants,
+}
+
+impl Related<super::educations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Educations.def()
+    }
+}
+
+impl Related<super::group_members::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::GroupMembers.def()
+    }
+}
+
+impl Related<super::participants::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Participants.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,21 @@
+use sea_orm::entity::prelude::*;
+use time::{Date, OffsetDateTime};
+
+/// Eén opgeslagen telling voor een metric op het moment van een run, zodat
+/// latere runs de groei of krimp t.o.v. de vorige ALV kunnen laten zien.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "metric_snapshots")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub run_at: OffsetDateTime,
+    pub study_year_start: Date,
+    pub metric_code: String,
+    pub study_code: Option<String>,
+    pub count: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
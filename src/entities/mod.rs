@@ -0,0 +1,21 @@
+pub mod activities;
+pub mod educations;
+pub mod group_members;
+pub mod members;
+pub mod metric_snapshots;
+pub mod participants;
+pub mod studies;
+
+// Niet elke entity wordt (nu al) elders bij naam gebruikt, maar deze module
+// exporteert ze consistent allemaal, net als sea-orm-cli's gegenereerde
+// `prelude`.
+#[allow(unused_imports)]
+pub use activities::Entity as Activities;
+#[allow(unused_imports)]
+pub use educations::Entity as Educations;
+#[allow(unused_imports)]
+pub use group_members::Entity as GroupMembers;
+pub use members::Entity as Members;
+pub use metric_snapshots::Entity as MetricSnapshots;
+#[allow(unused_imports)]
+pub use studies::Entity as Studies;
@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "studies")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub code: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::educations::Entity")]
+    Educations,
+}
+
+impl Related<super::educations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Educations.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
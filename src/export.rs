@@ -0,0 +1,108 @@
+use clap::ValueEnum;
+use color_eyre::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tabled::{Table, Tabled};
+
+/// Eén rij van een metric-resultaat: een sleutel (bv. een studiecode) met
+/// de bijbehorende telling.
+#[derive(Debug, Clone, Serialize, Tabled)]
+pub struct MetricRow {
+    pub key: String,
+    pub count: i64,
+}
+
+/// Een metric, losgekoppeld van de query die hem berekend heeft, zodat
+/// dezelfde data als tabel, JSON of CSV weggeschreven kan worden.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricResult {
+    pub code: String,
+    pub label: String,
+    pub rows: Vec<MetricRow>,
+    pub sum: i64,
+}
+
+impl MetricResult {
+    /// Een metric die uit één enkele telling bestaat (bv. A3, A4, A12).
+    pub fn single(code: &str, label: &str, count: i64) -> Self {
+        Self {
+            code: code.to_string(),
+            label: label.to_string(),
+            rows: vec![MetricRow {
+                key: code.to_string(),
+                count,
+            }],
+            sum: count,
+        }
+    }
+
+    /// Een metric die per sleutel (bv. studiecode) een telling heeft (A2, A6, A11).
+    pub fn by_key(code: &str, label: &str, rows: Vec<(String, i64)>) -> Self {
+        let sum = rows.iter().map(|(_, count)| *count).sum();
+        Self {
+            code: code.to_string(),
+            label: label.to_string(),
+            rows: rows
+                .into_iter()
+                .map(|(key, count)| MetricRow { key, count })
+                .collect(),
+            sum,
+        }
+    }
+}
+
+/// Uitvoerformaat voor de metrics, te kiezen met `--format`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+/// Schrijft alle metrics in het gekozen formaat naar `output`, of naar
+/// stdout als geen pad gegeven is.
+pub fn write(results: &[MetricResult], format: Format, output: Option<&Path>) -> Result<()> {
+    let rendered = match format {
+        Format::Table => render_table(results),
+        Format::Json => serde_json::to_string_pretty(results)?,
+        Format::Csv => render_csv(results)?,
+    };
+
+    match output {
+        Some(path) => fs::write(path, rendered)?,
+        None => println!("{rendered}"),
+    }
+
+    Ok(())
+}
+
+fn render_table(results: &[MetricResult]) -> String {
+    let mut out = String::new();
+
+    for result in results {
+        out.push_str(&format!("{} - {}\n", result.code, result.label));
+        out.push_str(&Table::new(&result.rows).to_string());
+        out.push_str(&format!("\nSum: {}\n\n", result.sum));
+    }
+
+    out
+}
+
+fn render_csv(results: &[MetricResult]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(["code", "label", "key", "count"])?;
+
+    for result in results {
+        for row in &result.rows {
+            writer.write_record([
+                result.code.as_str(),
+                result.label.as_str(),
+                row.key.as_str(),
+                &row.count.to_string(),
+            ])?;
+        }
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
@@ -0,0 +1,177 @@
+use sea_orm::sea_query::{Expr, Func};
+use sea_orm::{ColumnTrait, Condition};
+use time::Date;
+
+/// Optionele onderdelen van een metric-query. Elk onderdeel levert een
+/// losse, typed [`Condition`] op tegen de kolom die de aanroeper meegeeft,
+/// en wordt alleen meegenomen als het gezet is.
+#[derive(Default, Clone)]
+pub struct MetricFilter {
+    from: Option<Date>,
+    strict_from: bool,
+    to: Option<Date>,
+    study_codes: Option<Vec<String>>,
+    active_only: bool,
+    activity_pattern: Option<String>,
+}
+
+impl MetricFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from(mut self, from: Option<Date>) -> Self {
+        self.from = from;
+        self
+    }
+
+    /// Of `from` strikt (`>`) toegepast moet worden in plaats van inclusief
+    /// (`>=`). Gebruik dit voor een impliciet, van `study_year_start`
+    /// afgeleid ondergrens; zet het uit zodra de gebruiker zelf `--from`
+    /// heeft opgegeven, dan is een inclusieve ondergrens intuïtiever.
+    pub fn strict_from(mut self, strict_from: bool) -> Self {
+        self.strict_from = strict_from;
+        self
+    }
+
+    pub fn to(mut self, to: Option<Date>) -> Self {
+        self.to = to;
+        self
+    }
+
+    pub fn study_codes(mut self, study_codes: Option<Vec<String>>) -> Self {
+        self.study_codes = study_codes;
+        self
+    }
+
+    pub fn active_only(mut self, active_only: bool) -> Self {
+        self.active_only = active_only;
+        self
+    }
+
+    pub fn activity_pattern(mut self, activity_pattern: Option<String>) -> Self {
+        self.activity_pattern = activity_pattern;
+        self
+    }
+
+    /// Of de query beperkt moet worden tot leden die lid zijn van een groep.
+    pub fn is_active_only(&self) -> bool {
+        self.active_only
+    }
+
+    /// Bouwt de `> from` of `>= from` (zie [`Self::strict_from`]) en
+    /// `<= to` conditie tegen `column`, typisch een `join_date` of
+    /// `start_date` kolom.
+    pub fn date_condition<C: ColumnTrait>(&self, column: C) -> Condition {
+        let mut condition = Condition::all();
+
+        if let Some(from) = self.from {
+            condition = condition.add(if self.strict_from {
+                column.gt(from)
+            } else {
+                column.gte(from)
+            });
+        }
+        if let Some(to) = self.to {
+            condition = condition.add(column.lte(to));
+        }
+
+        condition
+    }
+
+    /// Bouwt de `IN (...)` conditie tegen `column` als er studie-codes gezet zijn.
+    pub fn study_code_condition<C: ColumnTrait>(&self, column: C) -> Condition {
+        let mut condition = Condition::all();
+
+        if let Some(study_codes) = &self.study_codes {
+            condition = condition.add(column.is_in(study_codes.clone()));
+        }
+
+        condition
+    }
+
+    /// Bouwt de naams-patroon conditie tegen `column` als er een patroon gezet
+    /// is: hoofdletterongevoelig en geankerd aan het begin van de naam, net
+    /// als de oorspronkelijke `name.to_lowercase().starts_with(...)` check.
+    pub fn activity_pattern_condition<C: ColumnTrait>(&self, column: C) -> Condition {
+        let mut condition = Condition::all();
+
+        if let Some(pattern) = &self.activity_pattern {
+            let prefix = format!("{}%", pattern.to_lowercase());
+            condition = condition.add(Expr::expr(Func::lower(Expr::col(column))).like(prefix));
+        }
+
+        condition
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MetricFilter;
+    use crate::entities::activities;
+    use sea_orm::sea_query::{PostgresQueryBuilder, Query};
+    use time::macros::date;
+
+    fn render(condition: sea_orm::Condition) -> String {
+        Query::select()
+            .column(activities::Column::Id)
+            .from(activities::Entity)
+            .cond_where(condition)
+            .to_string(PostgresQueryBuilder)
+    }
+
+    #[test]
+    fn date_condition_is_unbounded_without_from_or_to() {
+        let filter = MetricFilter::new();
+        let sql = render(filter.date_condition(activities::Column::StartDate));
+
+        assert!(!sql.contains("start_date"));
+    }
+
+    #[test]
+    fn date_condition_adds_gte_and_lte() {
+        let filter = MetricFilter::new()
+            .from(Some(date!(2024 - 09 - 01)))
+            .to(Some(date!(2025 - 08 - 31)));
+        let sql = render(filter.date_condition(activities::Column::StartDate));
+
+        assert!(sql.contains(">= '2024-09-01'"));
+        assert!(sql.contains("<= '2025-08-31'"));
+    }
+
+    #[test]
+    fn date_condition_uses_gt_when_from_is_exclusive() {
+        let filter = MetricFilter::new()
+            .from(Some(date!(2024 - 09 - 01)))
+            .strict_from(true);
+        let sql = render(filter.date_condition(activities::Column::StartDate));
+
+        assert!(sql.contains("> '2024-09-01'"));
+        assert!(!sql.contains(">= '2024-09-01'"));
+    }
+
+    #[test]
+    fn study_code_condition_is_unbounded_without_study_codes() {
+        let filter = MetricFilter::new();
+        let sql = render(filter.study_code_condition(activities::Column::Name));
+
+        assert!(!sql.contains("IN ("));
+    }
+
+    #[test]
+    fn study_code_condition_adds_in_clause() {
+        let filter = MetricFilter::new().study_codes(Some(vec!["INF".to_string(), "BIT".to_string()]));
+        let sql = render(filter.study_code_condition(activities::Column::Name));
+
+        assert!(sql.contains("IN ('INF', 'BIT')"));
+    }
+
+    #[test]
+    fn activity_pattern_condition_is_case_insensitive_prefix() {
+        let filter = MetricFilter::new().activity_pattern(Some("extern".to_string()));
+        let sql = render(filter.activity_pattern_condition(activities::Column::Name));
+
+        assert!(sql.contains("LOWER"));
+        assert!(sql.contains("LIKE 'extern%'"));
+    }
+}
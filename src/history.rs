@@ -0,0 +1,83 @@
+use crate::entities::{metric_snapshots, MetricSnapshots};
+use color_eyre::Result;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+use time::Date;
+
+/// Eén opgeslagen telling voor een metric op het moment van een run, zodat
+/// latere runs de groei of krimp t.o.v. de vorige ALV kunnen laten zien.
+#[derive(Debug, Clone)]
+pub struct MetricSnapshot {
+    pub metric_code: String,
+    pub study_code: Option<String>,
+    pub count: i64,
+}
+
+/// Haalt de meest recente telling van een eerdere run op voor dezelfde
+/// metric (en studiecode), zodat de delta t.o.v. de vorige ALV getoond kan
+/// worden. Geeft `None` terug als er nog geen eerdere run is.
+pub async fn previous_count(
+    driver: &DatabaseConnection,
+    study_year_start: Date,
+    metric_code: &str,
+    study_code: Option<&str>,
+) -> Result<Option<i64>> {
+    let mut query = MetricSnapshots::find()
+        .filter(metric_snapshots::Column::MetricCode.eq(metric_code))
+        .filter(metric_snapshots::Column::StudyYearStart.lt(study_year_start));
+
+    query = match study_code {
+        Some(code) => query.filter(metric_snapshots::Column::StudyCode.eq(code)),
+        None => query.filter(metric_snapshots::Column::StudyCode.is_null()),
+    };
+
+    let snapshot = query
+        .order_by_desc(metric_snapshots::Column::StudyYearStart)
+        .order_by_desc(metric_snapshots::Column::RunAt)
+        .one(driver)
+        .await?;
+
+    Ok(snapshot.map(|snapshot| snapshot.count))
+}
+
+/// Formatteert een delta als `+12`/`-3`/`0`, of `-` als er nog geen vorige run is.
+pub fn format_delta(previous: Option<i64>, current: i64) -> String {
+    match previous {
+        Some(previous) => {
+            let delta = current - previous;
+            if delta > 0 {
+                format!("+{delta}")
+            } else {
+                delta.to_string()
+            }
+        }
+        None => "-".to_string(),
+    }
+}
+
+/// Schrijft de snapshots van deze run weg, zodat een volgende run er een
+/// delta tegen kan berekenen.
+pub async fn record(
+    driver: &DatabaseConnection,
+    study_year_start: Date,
+    snapshots: &[MetricSnapshot],
+) -> Result<()> {
+    for snapshot in snapshots {
+        metric_snapshots::ActiveModel {
+            id: ActiveValue::NotSet,
+            run_at: ActiveValue::Set(
+                time::OffsetDateTime::now_utc(),
+            ),
+            study_year_start: ActiveValue::Set(study_year_start),
+            metric_code: ActiveValue::Set(snapshot.metric_code.clone()),
+            study_code: ActiveValue::Set(snapshot.study_code.clone()),
+            count: ActiveValue::Set(snapshot.count),
+        }
+        .insert(driver)
+        .await?;
+    }
+
+    Ok(())
+}
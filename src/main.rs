@@ -1,9 +1,31 @@
+mod anon;
+mod config;
+mod entities;
+mod export;
+mod filter;
+mod history;
+mod migrations;
+
 use clap::Parser;
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
-use sqlx::postgres::PgConnectOptions;
-use sqlx::{FromRow, PgPool};
+use entities::Members;
+use entities::{activities, educations, members, participants, studies};
+use export::{Format, MetricResult};
+use filter::MetricFilter;
+use history::MetricSnapshot;
+use migrations::Migrator;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, Database, DatabaseConnection, EntityTrait,
+    FromQueryResult, JoinType, PaginatorTrait, QueryFilter, QuerySelect, RelationTrait, Select,
+    Statement,
+};
+use sea_orm_migration::MigratorTrait;
+use std::path::PathBuf;
 use tabled::{Table, Tabled};
-use time::macros::format_description;
+use time::macros::{date, format_description};
 use time::Date;
 use tracing::info;
 use tracing_subscriber::fmt::layer;
@@ -11,18 +33,74 @@ use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{registry, EnvFilter};
 
+/// Studies met een `study_id` tot en met deze waarde tellen als bachelor.
+const BACHELOR_MAX_STUDY_ID: i32 = 4;
+/// Studies met een `study_id` vanaf deze waarde tellen als master.
+const MASTER_MIN_STUDY_ID: i32 = 5;
+/// Standaard startpunt van de ledenreeks in A8, het eerste ALV-jaar.
+const MEMBERS_SINCE: Date = date!(2010 - 08 - 01);
+/// Naamspatroon waarmee "extern" activiteiten in A13 herkend worden.
+const EXTERN_ACTIVITY_PATTERN: &str = "extern";
+
 #[derive(Parser)]
 pub struct Args {
-    /// Naam van de database, normaalgesproken `koala`
-    db_name: String,
-    /// Naam van de gebruiker voor de database, noormaalgesproken `koala_manual`
-    db_user: String,
-    /// Wachtwoord van de gebruiker voor de database, vraag deze op bij de ITCrowd
-    db_password: String,
     /// Datum van de start van het studiejaar, in het formaat `yyyy-mm-dd`
     study_year_start: String,
     /// Datum van de dag na de laatste NOVA activiteit, in het formaat `yyyy-mm-dd`
     date_after_nova: String,
+
+    /// Naam van de database, normaalgesproken `koala`. Kan ook via de
+    /// omgevingsvariabele `KALFJE_DB_NAME` of `--config` gezet worden.
+    #[arg(long, env = "KALFJE_DB_NAME")]
+    db_name: Option<String>,
+    /// Naam van de gebruiker voor de database, noormaalgesproken
+    /// `koala_manual`. Kan ook via `KALFJE_DB_USER` of `--config` gezet worden.
+    #[arg(long, env = "KALFJE_DB_USER")]
+    db_user: Option<String>,
+    /// Hostnaam of IP-adres van de database, standaard `127.0.0.1`
+    #[arg(long, env = "KALFJE_DB_HOST")]
+    host: Option<String>,
+    /// Poort van de database, standaard `5432`
+    #[arg(long, env = "KALFJE_DB_PORT")]
+    port: Option<u16>,
+    /// SSL-modus voor de database-verbinding, standaard `prefer`
+    #[arg(long, env = "KALFJE_DB_SSLMODE")]
+    sslmode: Option<String>,
+    /// Pad naar een TOML config-bestand met connectiegegevens, als aanvulling
+    /// op (met lagere prioriteit dan) argumenten en omgevingsvariabelen
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Begin van het datumvenster voor de metrics (yyyy-mm-dd), standaard het
+    /// begin van het studiejaar
+    #[arg(long)]
+    from: Option<String>,
+    /// Einde van het datumvenster voor de metrics (yyyy-mm-dd), standaard
+    /// ongelimiteerd
+    #[arg(long)]
+    to: Option<String>,
+    /// Kommagescheiden lijst van studie-codes om de metrics op te filteren
+    #[arg(long, value_delimiter = ',')]
+    study: Option<Vec<String>>,
+    /// Alleen leden meenemen die lid zijn van een groep (actieve leden)
+    #[arg(long)]
+    active_only: bool,
+
+    /// Uitvoerformaat voor de metrics
+    #[arg(long, value_enum, default_value = "table")]
+    format: Format,
+    /// Pad om de metrics naartoe weg te schrijven, standaard stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Zout voor het pseudonimiseren van lid-ids in de detail-export
+    /// (zie `--detailed`), nooit het echte lid-id of de naam
+    #[arg(long, alias = "hash-salt")]
+    salt: Option<String>,
+    /// Voeg een pseudonieme per-lid detail-export toe aan A12/A13 met
+    /// gehashte lid-tokens naast activiteit-ids, vereist `--salt`
+    #[arg(long)]
+    detailed: bool,
 }
 
 #[tokio::main]
@@ -38,229 +116,635 @@ async fn main() -> Result<()> {
         env!("CARGO_PKG_AUTHORS")
     );
 
-    let driver = open_database(&args.db_name, &args.db_user, &args.db_password).await?;
+    let file_config = config::FileConfig::load(args.config.as_deref())?;
+
+    let db_name = args
+        .db_name
+        .or(file_config.db_name)
+        .ok_or_else(|| eyre!("geen database-naam opgegeven (argument, KALFJE_DB_NAME, of --config)"))?;
+    let db_user = args
+        .db_user
+        .or(file_config.db_user)
+        .ok_or_else(|| eyre!("geen database-gebruiker opgegeven (argument, KALFJE_DB_USER, of --config)"))?;
+    // Wachtwoord mag bewust geen CLI-argument zijn (dat lekt via
+    // shell-historie en de process-tabel), dus is er geen `args.db_password`
+    // en wordt de omgevingsvariabele hier handmatig gelezen in plaats van
+    // via clap's `env = "..."`.
+    let db_password = match std::env::var("KALFJE_DB_PASSWORD")
+        .ok()
+        .or(file_config.db_password)
+    {
+        Some(db_password) => db_password,
+        None => rpassword::prompt_password("Wachtwoord voor de database: ")?,
+    };
+    let host = args
+        .host
+        .or(file_config.host)
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+    let port = args.port.or(file_config.port).unwrap_or(5432);
+    let sslmode = args
+        .sslmode
+        .or(file_config.sslmode)
+        .unwrap_or_else(|| "prefer".to_string());
+
+    let driver = open_database(&host, port, &db_name, &db_user, &db_password, &sslmode).await?;
     info!("Connected to database");
 
-    let study_year_start = Date::parse(
-        &args.study_year_start,
-        format_description!("[year]-[month]-[day]"),
-    )?;
-    let date_after_nova = Date::parse(
-        &args.date_after_nova,
-        format_description!("[year]-[month]-[day]"),
-    )?;
+    Migrator::up(&driver, None).await?;
+    info!("Migrations up to date");
+
+    let date_format = format_description!("[year]-[month]-[day]");
+    let study_year_start = Date::parse(&args.study_year_start, &date_format)?;
+    let date_after_nova = Date::parse(&args.date_after_nova, &date_format)?;
+    let from = args
+        .from
+        .as_deref()
+        .map(|d| Date::parse(d, &date_format))
+        .transpose()?;
+    let to = args
+        .to
+        .as_deref()
+        .map(|d| Date::parse(d, &date_format))
+        .transpose()?;
+
+    if args.detailed && args.salt.is_none() {
+        return Err(eyre!("--detailed vereist --salt (of --hash-salt)"));
+    }
 
     info!("Collecting and printing metrics.");
-    collect_and_print(&driver, study_year_start, date_after_nova).await?;
+    collect_and_print(
+        &driver,
+        study_year_start,
+        date_after_nova,
+        from,
+        to,
+        args.study,
+        args.active_only,
+        args.format,
+        args.output,
+        args.salt,
+        args.detailed,
+    )
+    .await?;
 
     info!("Done");
     Ok(())
 }
 
-#[derive(FromRow, Tabled)]
+#[derive(Debug, FromQueryResult, Tabled)]
 pub struct CodeCount {
     code: String,
     count: i64,
 }
 
-#[derive(FromRow, Tabled)]
+#[derive(Debug, FromQueryResult, Tabled)]
 pub struct JoinYearMembers {
     join_year: i32,
     members: i64,
 }
 
-#[derive(FromRow, Tabled)]
-pub struct OnlyCount {
+#[derive(Tabled)]
+pub struct CodeCountDelta {
+    code: String,
     count: i64,
+    #[tabled(rename = "t.o.v. vorige ALV")]
+    delta: String,
 }
 
-async fn collect_and_print(
-    driver: &PgPool,
-    study_year_start: Date,
-    date_after_nove: Date,
-) -> Result<()> {
-    let a2: Vec<CodeCount> = sqlx::query_as(
-        "SELECT studies.code, COUNT(DISTINCT(members.id)) FROM members
-                JOIN educations ON members.id = educations.member_id
-                JOIN studies ON educations.study_id = studies.id
-            WHERE educations.status = 0
-            GROUP BY studies.code",
-    )
-    .fetch_all(&*driver)
-    .await?;
-
-    println!("A2 - Verdeling studies");
-    println!("{}", Table::new(&a2).to_string());
-    println!("Sum: {}", a2.iter().map(|x| x.count).sum::<i64>());
-    println!();
+#[derive(Tabled)]
+pub struct OnlyCountDelta {
+    count: i64,
+    #[tabled(rename = "t.o.v. vorige ALV")]
+    delta: String,
+}
 
-    let a3: OnlyCount = sqlx::query_as(
-        "SELECT COUNT(DISTINCT(members.id)) FROM members
-                JOIN educations ON members.id = educations.member_id
-                JOIN studies ON educations.study_id = studies.id
-            WHERE educations.status = 0 AND members.join_date > $1",
-    )
-    .bind(&study_year_start)
-    .fetch_one(&*driver)
-    .await?;
+/// Bouwt de delta-variant van een aantal [`CodeCount`] rijen voor metric
+/// `metric_code`, op basis van de laatst opgeslagen snapshot per studiecode,
+/// en verzamelt de nieuwe tellingen in `snapshots` voor de volgende run.
+async fn with_code_deltas(
+    driver: &DatabaseConnection,
+    study_year_start: Date,
+    metric_code: &str,
+    rows: Vec<CodeCount>,
+    snapshots: &mut Vec<MetricSnapshot>,
+) -> Result<Vec<CodeCountDelta>> {
+    let mut out = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        let previous =
+            history::previous_count(driver, study_year_start, metric_code, Some(&row.code))
+                .await?;
+
+        snapshots.push(MetricSnapshot {
+            metric_code: metric_code.to_string(),
+            study_code: Some(row.code.clone()),
+            count: row.count,
+        });
+
+        out.push(CodeCountDelta {
+            code: row.code,
+            count: row.count,
+            delta: history::format_delta(previous, row.count),
+        });
+    }
 
-    println!("A3 - Nieuwe leden");
-    println!("{}", Table::new(vec![a3]).to_string());
-    println!();
+    Ok(out)
+}
 
-    let a4: OnlyCount = sqlx::query_as(
-        "SELECT COUNT(DISTINCT(members.id)) FROM members
-                INNER JOIN educations
-                ON members.id = educations.member_id
-            WHERE members.join_date > $1 AND educations.study_id < 5",
-    )
-    .bind(&study_year_start)
-    .fetch_one(&*driver)
-    .await?;
+/// Bouwt de delta-variant van een enkele telling voor metric `metric_code`,
+/// en verzamelt de nieuwe telling in `snapshots` voor de volgende run.
+async fn with_only_delta(
+    driver: &DatabaseConnection,
+    study_year_start: Date,
+    metric_code: &str,
+    count: i64,
+    snapshots: &mut Vec<MetricSnapshot>,
+) -> Result<OnlyCountDelta> {
+    let previous = history::previous_count(driver, study_year_start, metric_code, None).await?;
+
+    snapshots.push(MetricSnapshot {
+        metric_code: metric_code.to_string(),
+        study_code: None,
+        count,
+    });
+
+    Ok(OnlyCountDelta {
+        count,
+        delta: history::format_delta(previous, count),
+    })
+}
 
-    println!("A4 - Nieuwe bachelor");
-    println!("{}", Table::new(vec![a4]).to_string());
-    println!();
+/// Voegt de join naar `studies` toe als er op studie gefilterd wordt, en de
+/// join naar `group_members` toe als `--active-only` gezet is. Gedeeld door
+/// A12/A12-detail/A13/A13-detail, die anders allemaal dezelfde twee
+/// conditionele joins zouden herhalen.
+fn with_study_and_active_only_joins(
+    mut query: Select<Members>,
+    has_study_filter: bool,
+    active_only: bool,
+) -> Select<Members> {
+    if has_study_filter {
+        query = query
+            .join(JoinType::InnerJoin, members::Relation::Educations.def())
+            .join(JoinType::InnerJoin, educations::Relation::Studies.def());
+    }
+    if active_only {
+        query = query.join(JoinType::InnerJoin, members::Relation::GroupMembers.def());
+    }
+    query
+}
 
-    let a5: OnlyCount = sqlx::query_as(
-        "SELECT COUNT(DISTINCT(members.id)) FROM members
-                INNER JOIN educations
-                ON members.id = educations.member_id
-            WHERE members.join_date > $1 AND educations.study_id > 4",
-    )
-    .bind(&study_year_start)
-    .fetch_one(&*driver)
-    .await?;
+#[allow(clippy::too_many_arguments)]
+async fn collect_and_print(
+    driver: &DatabaseConnection,
+    study_year_start: Date,
+    date_after_nova: Date,
+    from: Option<Date>,
+    to: Option<Date>,
+    study: Option<Vec<String>>,
+    active_only: bool,
+    format: Format,
+    output: Option<PathBuf>,
+    salt: Option<String>,
+    detailed: bool,
+) -> Result<()> {
+    let mut snapshots: Vec<MetricSnapshot> = Vec::new();
+    let mut results: Vec<MetricResult> = Vec::new();
+    let print_tables = matches!(format, Format::Table);
+    // Een gefilterde run (--study/--active-only/--from/--to) telt een ander
+    // deel van de leden dan de kale run waar de year-over-year delta op
+    // gebaseerd is; die snapshot zou de "vorige ALV"-telling van een latere
+    // onge-filterde run corrumperen, dus slaan we opslag dan over.
+    let is_filtered_run = study.is_some() || active_only || from.is_some() || to.is_some();
+
+    // Filter voor metrics die over alle leden gaan, niet alleen de nieuwe
+    // lichting van dit studiejaar. Zonder `--from`/`--to` is dit ongelimiteerd.
+    let all_members_filter = MetricFilter::new()
+        .from(from)
+        .to(to)
+        .study_codes(study.clone())
+        .active_only(active_only);
+
+    // Filter voor metrics die standaard alleen nieuwe leden van dit
+    // studiejaar meetellen, tenzij `--from` dat expliciet overschrijft. Zonder
+    // `--from` is de ondergrens strikt (`>`), net als de oorspronkelijke
+    // `join_date > study_year_start`; met een expliciete `--from` is de
+    // ondergrens inclusief (`>=`), wat intuïtiever is voor een zelfgekozen venster.
+    let new_members_filter = MetricFilter::new()
+        .from(Some(from.unwrap_or(study_year_start)))
+        .strict_from(from.is_none())
+        .to(to)
+        .study_codes(study.clone())
+        .active_only(active_only);
+
+    let a2_condition = Condition::all()
+        .add(educations::Column::Status.eq(0))
+        .add(all_members_filter.date_condition(members::Column::JoinDate))
+        .add(all_members_filter.study_code_condition(studies::Column::Code));
+    let mut a2_query = Members::find()
+        .join(JoinType::InnerJoin, members::Relation::Educations.def())
+        .join(JoinType::InnerJoin, educations::Relation::Studies.def())
+        .filter(a2_condition)
+        .select_only()
+        .column(studies::Column::Code)
+        .column_as(Expr::col((Members, members::Column::Id)).count_distinct(), "count")
+        .group_by(studies::Column::Code);
+    if all_members_filter.is_active_only() {
+        a2_query = a2_query.join(JoinType::InnerJoin, members::Relation::GroupMembers.def());
+    }
+    let a2: Vec<CodeCount> = a2_query.into_model::<CodeCount>().all(driver).await?;
+
+    let sum_a2 = a2.iter().map(|x| x.count).sum::<i64>();
+    results.push(MetricResult::by_key(
+        "A2",
+        "Verdeling studies",
+        a2.iter().map(|x| (x.code.clone(), x.count)).collect(),
+    ));
+    let a2 = with_code_deltas(driver, study_year_start, "A2", a2, &mut snapshots).await?;
+
+    if print_tables {
+        println!("A2 - Verdeling studies");
+        println!("{}", Table::new(&a2));
+        println!("Sum: {}", sum_a2);
+        println!();
+    }
 
-    println!("A5 - Nieuew master");
-    println!("{}", Table::new(vec![a5]).to_string());
-    println!();
+    let a3_condition = Condition::all()
+        .add(educations::Column::Status.eq(0))
+        .add(new_members_filter.date_condition(members::Column::JoinDate))
+        .add(new_members_filter.study_code_condition(studies::Column::Code));
+    let mut a3_query = Members::find()
+        .join(JoinType::InnerJoin, members::Relation::Educations.def())
+        .join(JoinType::InnerJoin, educations::Relation::Studies.def())
+        .filter(a3_condition);
+    if new_members_filter.is_active_only() {
+        a3_query = a3_query.join(JoinType::InnerJoin, members::Relation::GroupMembers.def());
+    }
+    let a3 = a3_query
+        .select_only()
+        .column(members::Column::Id)
+        .distinct()
+        .count(driver)
+        .await? as i64;
+
+    results.push(MetricResult::single("A3", "Nieuwe leden", a3));
+    let a3 = with_only_delta(driver, study_year_start, "A3", a3, &mut snapshots).await?;
+
+    if print_tables {
+        println!("A3 - Nieuwe leden");
+        println!("{}", Table::new(vec![a3]));
+        println!();
+    }
 
-    let a6: Vec<CodeCount> = sqlx::query_as(
-        "SELECT studies.code, COUNT(DISTINCT(members.id)) FROM members
-                JOIN educations ON members.id = educations.member_id
-                JOIN studies ON educations.study_id = studies.id
-            WHERE educations.status = 0 AND members.join_date  > $1 group by studies.code",
-    )
-    .bind(&study_year_start)
-    .fetch_all(&*driver)
-    .await?;
+    let a4_condition = Condition::all()
+        .add(educations::Column::StudyId.lte(BACHELOR_MAX_STUDY_ID))
+        .add(new_members_filter.date_condition(members::Column::JoinDate));
+    let mut a4_query = Members::find()
+        .join(JoinType::InnerJoin, members::Relation::Educations.def())
+        .filter(a4_condition);
+    if new_members_filter.is_active_only() {
+        a4_query = a4_query.join(JoinType::InnerJoin, members::Relation::GroupMembers.def());
+    }
+    let a4 = a4_query
+        .select_only()
+        .column(members::Column::Id)
+        .distinct()
+        .count(driver)
+        .await? as i64;
+
+    results.push(MetricResult::single("A4", "Nieuwe bachelor", a4));
+    let a4 = with_only_delta(driver, study_year_start, "A4", a4, &mut snapshots).await?;
+
+    if print_tables {
+        println!("A4 - Nieuwe bachelor");
+        println!("{}", Table::new(vec![a4]));
+        println!();
+    }
 
-    println!("A6 - Verdeling studies nieuwe leden");
-    println!("{}", Table::new(&a6).to_string());
-    println!("Sum: {}", a6.iter().map(|x| x.count).sum::<i64>());
-    println!();
+    let a5_condition = Condition::all()
+        .add(educations::Column::StudyId.gte(MASTER_MIN_STUDY_ID))
+        .add(new_members_filter.date_condition(members::Column::JoinDate));
+    let mut a5_query = Members::find()
+        .join(JoinType::InnerJoin, members::Relation::Educations.def())
+        .filter(a5_condition);
+    if new_members_filter.is_active_only() {
+        a5_query = a5_query.join(JoinType::InnerJoin, members::Relation::GroupMembers.def());
+    }
+    let a5 = a5_query
+        .select_only()
+        .column(members::Column::Id)
+        .distinct()
+        .count(driver)
+        .await? as i64;
+
+    results.push(MetricResult::single("A5", "Nieuw master", a5));
+    let a5 = with_only_delta(driver, study_year_start, "A5", a5, &mut snapshots).await?;
+
+    if print_tables {
+        println!("A5 - Nieuew master");
+        println!("{}", Table::new(vec![a5]));
+        println!();
+    }
 
-    let a7: OnlyCount = sqlx::query_as(
-        "SELECT COUNT(DISTINCT(member_id)) FROM members INNER JOIN group_members
-            ON members.id = group_members.member_id WHERE members.join_date > $1",
-    )
-    .bind(&study_year_start)
-    .fetch_one(&*driver)
-    .await?;
+    let a6_condition = Condition::all()
+        .add(educations::Column::Status.eq(0))
+        .add(new_members_filter.date_condition(members::Column::JoinDate))
+        .add(new_members_filter.study_code_condition(studies::Column::Code));
+    let mut a6_query = Members::find()
+        .join(JoinType::InnerJoin, members::Relation::Educations.def())
+        .join(JoinType::InnerJoin, educations::Relation::Studies.def())
+        .filter(a6_condition)
+        .select_only()
+        .column(studies::Column::Code)
+        .column_as(Expr::col((Members, members::Column::Id)).count_distinct(), "count")
+        .group_by(studies::Column::Code);
+    if new_members_filter.is_active_only() {
+        a6_query = a6_query.join(JoinType::InnerJoin, members::Relation::GroupMembers.def());
+    }
+    let a6: Vec<CodeCount> = a6_query.into_model::<CodeCount>().all(driver).await?;
+
+    let sum_a6 = a6.iter().map(|x| x.count).sum::<i64>();
+    results.push(MetricResult::by_key(
+        "A6",
+        "Verdeling studies nieuwe leden",
+        a6.iter().map(|x| (x.code.clone(), x.count)).collect(),
+    ));
+    let a6 = with_code_deltas(driver, study_year_start, "A6", a6, &mut snapshots).await?;
+
+    if print_tables {
+        println!("A6 - Verdeling studies nieuwe leden");
+        println!("{}", Table::new(&a6));
+        println!("Sum: {}", sum_a6);
+        println!();
+    }
 
-    println!("A7 - Nieuwe actieve leden");
-    println!("{}", Table::new(vec![a7]).to_string());
-    println!();
+    let a7_condition = new_members_filter.date_condition(members::Column::JoinDate);
+    let a7 = Members::find()
+        .join(JoinType::InnerJoin, members::Relation::GroupMembers.def())
+        .filter(a7_condition)
+        .select_only()
+        .column(members::Column::Id)
+        .distinct()
+        .count(driver)
+        .await? as i64;
+
+    results.push(MetricResult::single("A7", "Nieuwe actieve leden", a7));
+    let a7 = with_only_delta(driver, study_year_start, "A7", a7, &mut snapshots).await?;
+
+    if print_tables {
+        println!("A7 - Nieuwe actieve leden");
+        println!("{}", Table::new(vec![a7]));
+        println!();
+    }
 
-    let a8: Vec<JoinYearMembers> = sqlx::query_as(
+    let a8: Vec<JoinYearMembers> = JoinYearMembers::find_by_statement(Statement::from_sql_and_values(
+        driver.get_database_backend(),
         "SELECT
                 EXTRACT(YEAR FROM generate_series)::int as join_year,count(distinct(members.id)) filter (
     	            where members.join_date > generate_series and members.join_date <= generate_series + interval '1 year'
 	            ) as members
             FROM
-                generate_series('2010-08-01'::date, $1::date, '1 year') as generate_series
+                generate_series($1::date, $2::date, '1 year') as generate_series
             LEFT JOIN
                 members ON members.join_date > generate_series AND members.join_date <= generate_series + interval '1 year'
-            GROUP BY join_year;"
-    )
-        .bind(&study_year_start)
-        .fetch_all(&*driver)
+            GROUP BY join_year;",
+        [
+            from.unwrap_or(MEMBERS_SINCE).into(),
+            to.unwrap_or(study_year_start).into(),
+        ],
+    ))
+    .all(driver)
+    .await?;
+
+    if print_tables {
+        println!("A8 - Nieuwe leden sinds 2010");
+        println!("{}", Table::new(&a8));
+        println!();
+    }
+    results.push(MetricResult::by_key(
+        "A8",
+        "Nieuwe leden sinds 2010",
+        a8.iter()
+            .map(|x| (x.join_year.to_string(), x.members))
+            .collect(),
+    ));
+
+    let a11_condition = Condition::all()
+        .add(educations::Column::Status.eq(0))
+        .add(new_members_filter.date_condition(members::Column::JoinDate))
+        .add(new_members_filter.study_code_condition(studies::Column::Code));
+    let a11: Vec<CodeCount> = Members::find()
+        .join(JoinType::InnerJoin, members::Relation::GroupMembers.def())
+        .join(JoinType::InnerJoin, members::Relation::Educations.def())
+        .join(JoinType::InnerJoin, educations::Relation::Studies.def())
+        .filter(a11_condition)
+        .select_only()
+        .column(studies::Column::Code)
+        .column_as(Expr::col((Members, members::Column::Id)).count_distinct(), "count")
+        .group_by(studies::Column::Code)
+        .into_model::<CodeCount>()
+        .all(driver)
         .await?;
 
-    println!("A8 - Nieuwe leden sinds 2010");
-    println!("{}", Table::new(&a8).to_string());
-    println!();
-
-    let a11: Vec<CodeCount> = sqlx::query_as(
-        "SELECT studies.code , COUNT(DISTINCT(members.id)) FROM members
-                inner join group_members ON members.id = group_members.member_id
-                JOIN educations on members.id = educations.member_id
-                join studies on educations.study_id = studies.id
-            WHERE educations.status = 0
-                AND members.join_date  > $1
-            group by studies.code",
-    )
-    .bind(&study_year_start)
-    .fetch_all(&*driver)
-    .await?;
+    let sum_a11 = a11.iter().map(|x| x.count).sum::<i64>();
+    results.push(MetricResult::by_key(
+        "A11",
+        "Verdeling nieuwe actieve leden",
+        a11.iter().map(|x| (x.code.clone(), x.count)).collect(),
+    ));
+    let a11 = with_code_deltas(driver, study_year_start, "A11", a11, &mut snapshots).await?;
+
+    if print_tables {
+        println!("A11 - Verdeling nieuwe actieve leden");
+        println!("{}", Table::new(&a11));
+        println!(
+            "Sum: {} (Kan anders zijn dan het getal van A7, i.v.m dubbele studies)",
+            sum_a11
+        );
+        println!();
+    }
 
-    println!("A11 - Verdeling nieuwe actieve leden");
-    println!("{}", Table::new(&a11).to_string());
-    println!(
-        "Sum: {} (Kan anders zijn dan het getal van A7, i.v.m dubbele studies)",
-        a11.iter().map(|x| x.count).sum::<i64>()
+    let a12_condition = Condition::all()
+        .add(members::Column::JoinDate.gt(study_year_start))
+        .add(activities::Column::StartDate.gt(date_after_nova))
+        .add(new_members_filter.study_code_condition(studies::Column::Code));
+    let a12_query = with_study_and_active_only_joins(
+        Members::find()
+            .join(JoinType::InnerJoin, members::Relation::Participants.def())
+            .join(JoinType::InnerJoin, participants::Relation::Activities.def())
+            .filter(a12_condition),
+        study.is_some(),
+        new_members_filter.is_active_only(),
     );
-    println!();
-
-    let a12: OnlyCount = sqlx::query_as(
-        "SELECT COUNT(DISTINCT(lid_id)) FROM(SELECT DISTINCT(members.id) as lid_id,
-                members.first_name, members.last_name, participants.activity_id
-                FROM members INNER JOIN participants ON members.id = participants.member_id
-            WHERE members.join_date > $1) AS dinges INNER JOIN activities on
-                dinges.activity_id = activities.id WHERE activities.start_date > $2",
-    )
-    .bind(&study_year_start)
-    .bind(&date_after_nove)
-    .fetch_one(&*driver)
-    .await?;
+    let a12 = a12_query
+        .select_only()
+        .column(members::Column::Id)
+        .distinct()
+        .count(driver)
+        .await? as i64;
+
+    results.push(MetricResult::single("A12", "Sjaars bij activiteiten", a12));
+    let a12 = with_only_delta(driver, study_year_start, "A12", a12, &mut snapshots).await?;
+
+    if print_tables {
+        println!("A12 - Sjaars bij activiteiten");
+        println!("{}", Table::new(vec![a12]));
+        println!();
+    }
 
-    println!("A12 - Sjaars bij activiteiten");
-    println!("{}", Table::new(vec![a12]).to_string());
-    println!();
+    if let Some(salt) = salt.as_deref().filter(|_| detailed) {
+        let a12_detail_condition = Condition::all()
+            .add(members::Column::JoinDate.gt(study_year_start))
+            .add(activities::Column::StartDate.gt(date_after_nova))
+            .add(new_members_filter.study_code_condition(studies::Column::Code));
+        let a12_detail_query = with_study_and_active_only_joins(
+            Members::find()
+                .join(JoinType::InnerJoin, members::Relation::Participants.def())
+                .join(JoinType::InnerJoin, participants::Relation::Activities.def())
+                .filter(a12_detail_condition),
+            study.is_some(),
+            new_members_filter.is_active_only(),
+        );
+        let a12_detail: Vec<(i32, i32)> = a12_detail_query
+            .select_only()
+            .column(members::Column::Id)
+            .column(participants::Column::ActivityId)
+            .distinct()
+            .into_tuple()
+            .all(driver)
+            .await?;
+
+        let a12_detail = MetricResult::by_key(
+            "A12-detail",
+            "Sjaars bij activiteiten (pseudoniem per lid)",
+            a12_detail
+                .into_iter()
+                .map(|(member_id, activity_id)| {
+                    (anon::pseudonymize(salt, member_id), activity_id as i64)
+                })
+                .collect(),
+        );
+
+        if print_tables {
+            println!("A12-detail - Sjaars bij activiteiten (pseudoniem per lid)");
+            println!("{}", Table::new(&a12_detail.rows));
+            println!();
+        }
+
+        results.push(a12_detail);
+    }
 
-    #[derive(FromRow)]
-    struct IdName {
-        id: i32,
-        name: String,
+    let extern_filter =
+        MetricFilter::new().activity_pattern(Some(EXTERN_ACTIVITY_PATTERN.to_string()));
+    let a13_condition = Condition::all()
+        .add(extern_filter.activity_pattern_condition(activities::Column::Name))
+        .add(activities::Column::StartDate.gt(study_year_start))
+        .add(new_members_filter.study_code_condition(studies::Column::Code));
+    let a13_query = with_study_and_active_only_joins(
+        Members::find()
+            .join(JoinType::InnerJoin, members::Relation::Participants.def())
+            .join(JoinType::InnerJoin, participants::Relation::Activities.def())
+            .filter(a13_condition),
+        study.is_some(),
+        new_members_filter.is_active_only(),
+    );
+    let a13 = a13_query
+        .select_only()
+        .column(members::Column::Id)
+        .distinct()
+        .count(driver)
+        .await? as i64;
+
+    results.push(MetricResult::single(
+        "A13",
+        "Leden bij Extern activiteiten",
+        a13,
+    ));
+    let a13 = with_only_delta(driver, study_year_start, "A13", a13, &mut snapshots).await?;
+
+    if print_tables {
+        println!("A13 - Leden bij Extern activiteiten");
+        println!("{}", Table::new(vec![a13]));
+        println!();
     }
 
-    let extern_activities = sqlx::query_as("SELECT id,name FROM activities WHERE start_date > $1")
-        .bind(&study_year_start)
-        .fetch_all(&*driver)
-        .await?
-        .into_iter()
-        .filter(|act: &IdName| act.name.to_lowercase().trim().starts_with("extern"))
-        .map(|act| act.id)
-        .collect::<Vec<_>>();
-
-    let a13: OnlyCount = sqlx::query_as(
-        "SELECT COUNT(DISTINCT(lid_id)) FROM(SELECT DISTINCT(members.id) as lid_id,
-            participants.activity_id FROM
-            members INNER JOIN participants ON members.id = participants.member_id)
-            AS dinges INNER JOIN activities ON dinges.activity_id = activities.id WHERE activities.id IN (SELECT unnest($1::integer[]))"
-    )
-        .bind(extern_activities)
-        .fetch_one(&*driver)
-        .await?;
+    if let Some(salt) = salt.as_deref().filter(|_| detailed) {
+        let a13_detail_condition = Condition::all()
+            .add(extern_filter.activity_pattern_condition(activities::Column::Name))
+            .add(activities::Column::StartDate.gt(study_year_start))
+            .add(new_members_filter.study_code_condition(studies::Column::Code));
+        let a13_detail_query = with_study_and_active_only_joins(
+            Members::find()
+                .join(JoinType::InnerJoin, members::Relation::Participants.def())
+                .join(JoinType::InnerJoin, participants::Relation::Activities.def())
+                .filter(a13_detail_condition),
+            study.is_some(),
+            new_members_filter.is_active_only(),
+        );
+        let a13_detail: Vec<(i32, i32)> = a13_detail_query
+            .select_only()
+            .column(members::Column::Id)
+            .column(participants::Column::ActivityId)
+            .distinct()
+            .into_tuple()
+            .all(driver)
+            .await?;
+
+        let a13_detail = MetricResult::by_key(
+            "A13-detail",
+            "Leden bij Extern activiteiten (pseudoniem per lid)",
+            a13_detail
+                .into_iter()
+                .map(|(member_id, activity_id)| {
+                    (anon::pseudonymize(salt, member_id), activity_id as i64)
+                })
+                .collect(),
+        );
+
+        if print_tables {
+            println!("A13-detail - Leden bij Extern activiteiten (pseudoniem per lid)");
+            println!("{}", Table::new(&a13_detail.rows));
+            println!();
+        }
+
+        results.push(a13_detail);
+    }
+
+    if is_filtered_run {
+        info!("Sla opslag van snapshots over: deze run is gefilterd (--study/--active-only/--from/--to)");
+    } else {
+        history::record(driver, study_year_start, &snapshots).await?;
+    }
 
-    println!("A13 - Leden bij Extern activiteiten");
-    println!("{}", Table::new(vec![a13]).to_string());
-    println!();
+    // Zonder `--output` zijn de tabellen hierboven al naar stdout geprint, dus
+    // dan is er niets meer te doen voor `Format::Table`. Mét `--output` moet
+    // ook de tabel-opmaak naar bestand geschreven worden, anders doet
+    // `--output` niets als je geen `--format json|csv` meegeeft.
+    if !print_tables || output.is_some() {
+        export::write(&results, format, output.as_deref())?;
+    }
 
-    println!("Done. Heel veel success met de ALV â™¡");
+    if print_tables {
+        println!("Done. Heel veel success met de ALV ♡");
+    }
 
     Ok(())
 }
 
-async fn open_database(db_name: &str, user: &str, passw: &str) -> Result<PgPool> {
-    let opts = PgConnectOptions::new()
-        .host("127.0.0.1")
-        .database(db_name)
-        .username(user)
-        .password(passw);
-
-    Ok(PgPool::connect_with(opts).await?)
+async fn open_database(
+    host: &str,
+    port: u16,
+    db_name: &str,
+    user: &str,
+    passw: &str,
+    sslmode: &str,
+) -> Result<DatabaseConnection> {
+    // Gebruikersnaam en wachtwoord kunnen `/`, `#`, `?` of `%` bevatten, die
+    // anders de authority/path/query-grenzen van de URL zouden verstoren.
+    let user = utf8_percent_encode(user, NON_ALPHANUMERIC);
+    let passw = utf8_percent_encode(passw, NON_ALPHANUMERIC);
+    let url = format!("postgres://{user}:{passw}@{host}:{port}/{db_name}?sslmode={sslmode}");
+    Ok(Database::connect(&url).await?)
 }
 
 fn install_tracing() {
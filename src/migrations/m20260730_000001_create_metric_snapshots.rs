@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MetricSnapshots::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MetricSnapshots::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MetricSnapshots::RunAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(MetricSnapshots::StudyYearStart)
+                            .date()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MetricSnapshots::MetricCode)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MetricSnapshots::StudyCode).string())
+                    .col(
+                        ColumnDef::new(MetricSnapshots::Count)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MetricSnapshots::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MetricSnapshots {
+    Table,
+    Id,
+    RunAt,
+    StudyYearStart,
+    MetricCode,
+    StudyCode,
+    Count,
+}
@@ -0,0 +1,17 @@
+use sea_orm_migration::prelude::*;
+
+mod m20260730_000001_create_metric_snapshots;
+
+/// Voert de schema-migraties uit voor de tabellen die dit stuk gereedschap
+/// zelf beheert (momenteel alleen `metric_snapshots`). De koala-tabellen
+/// zelf worden beheerd door koala en hier alleen als entity gemodelleerd.
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![Box::new(
+            m20260730_000001_create_metric_snapshots::Migration,
+        )]
+    }
+}